@@ -0,0 +1,157 @@
+//! Snapshot diffing, for tracking page migration between NUMA nodes over time.
+//!
+//! Repeatedly sampling `numa_maps` is how callers watch pages migrate between nodes
+//! under the kernel's autonuma or an explicit `mbind`. [`NumaMap::diff`] matches ranges
+//! between two samples by base address, and reports per-node byte movement, newly
+//! appeared and disappeared ranges, and policy changes.
+
+use std::collections::BTreeMap;
+
+use crate::{NumaMap, Property, Range};
+
+/// Per-node byte deltas for a single range matched between two [`NumaMap`] snapshots.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RangeDiff {
+    /// The range's base address.
+    pub address: usize,
+    /// The policy in the previous snapshot, if it differed from the current one.
+    pub previous_policy: Option<String>,
+    /// Per-node byte deltas (`new - previous`) for every node either snapshot had an `N`
+    /// entry for.
+    pub node_deltas: BTreeMap<usize, i64>,
+}
+
+/// The result of [`NumaMap::diff`]ing two snapshots of the same process.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NumaDiff {
+    /// Matched ranges (by base address) whose policy or per-node byte counts changed.
+    pub changed: Vec<RangeDiff>,
+    /// Base addresses of ranges present in the new snapshot but not the previous one.
+    pub appeared: Vec<usize>,
+    /// Base addresses of ranges present in the previous snapshot but not the new one.
+    pub disappeared: Vec<usize>,
+    /// Net byte movement per node, summed across all matched ranges.
+    pub net_node_deltas: BTreeMap<usize, i64>,
+}
+
+impl NumaMap {
+    /// Diffs this (newer) snapshot against `previous`, matching ranges by base address
+    /// only (not also by policy): a range is considered the same range across snapshots
+    /// as long as its address is unchanged, even if its policy changed. Matching on
+    /// policy too would turn a policy change into a disappeared-then-appeared pair
+    /// instead of the `previous_policy` change reported on [`RangeDiff`].
+    ///
+    /// Ranges should be normalized first (see [`Range::normalize`]) so that deltas are in
+    /// bytes and therefore meaningful across samples.
+    #[must_use]
+    pub fn diff(&self, previous: &NumaMap) -> NumaDiff {
+        let previous_by_address: BTreeMap<usize, &Range> =
+            previous.ranges.iter().map(|r| (r.address, r)).collect();
+        let current_by_address: BTreeMap<usize, &Range> =
+            self.ranges.iter().map(|r| (r.address, r)).collect();
+
+        let mut diff = NumaDiff::default();
+        for (&address, current) in &current_by_address {
+            let Some(previous) = previous_by_address.get(&address) else {
+                diff.appeared.push(address);
+                continue;
+            };
+
+            let node_deltas = node_deltas(previous, current);
+            for (&node, &delta) in &node_deltas {
+                *diff.net_node_deltas.entry(node).or_insert(0) += delta;
+            }
+
+            let previous_policy = (previous.policy != current.policy).then(|| previous.policy.clone());
+            if previous_policy.is_some() || node_deltas.values().any(|&delta| delta != 0) {
+                diff.changed.push(RangeDiff {
+                    address,
+                    previous_policy,
+                    node_deltas,
+                });
+            }
+        }
+        for &address in previous_by_address.keys() {
+            if !current_by_address.contains_key(&address) {
+                diff.disappeared.push(address);
+            }
+        }
+        diff
+    }
+}
+
+/// Computes `new - previous` per node for a single matched range.
+fn node_deltas(previous: &Range, current: &Range) -> BTreeMap<usize, i64> {
+    let mut deltas: BTreeMap<usize, i64> = BTreeMap::new();
+    for property in &previous.properties {
+        if let Property::N(node, size) = *property {
+            *deltas.entry(node).or_insert(0) -= size as i64;
+        }
+    }
+    for property in &current.properties {
+        if let Property::N(node, size) = *property {
+            *deltas.entry(node).or_insert(0) += size as i64;
+        }
+    }
+    deltas
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Property::N;
+
+    fn range(address: usize, policy: &str, node_sizes: &[(usize, usize)]) -> Range {
+        Range {
+            address,
+            policy: policy.to_string(),
+            properties: node_sizes.iter().map(|&(node, size)| N(node, size)).collect(),
+        }
+    }
+
+    #[test]
+    fn test_diff_reports_node_movement() {
+        let previous = NumaMap {
+            ranges: vec![range(0x1000, "default", &[(0, 100), (1, 0)])],
+        };
+        let current = NumaMap {
+            ranges: vec![range(0x1000, "default", &[(0, 40), (1, 60)])],
+        };
+
+        let diff = current.diff(&previous);
+        assert_eq!(diff.net_node_deltas.get(&0), Some(&-60));
+        assert_eq!(diff.net_node_deltas.get(&1), Some(&60));
+        assert_eq!(diff.changed.len(), 1);
+        assert!(diff.appeared.is_empty());
+        assert!(diff.disappeared.is_empty());
+    }
+
+    #[test]
+    fn test_diff_reports_appeared_and_disappeared() {
+        let previous = NumaMap {
+            ranges: vec![range(0x1000, "default", &[(0, 10)])],
+        };
+        let current = NumaMap {
+            ranges: vec![range(0x2000, "default", &[(0, 10)])],
+        };
+
+        let diff = current.diff(&previous);
+        assert_eq!(diff.appeared, vec![0x2000]);
+        assert_eq!(diff.disappeared, vec![0x1000]);
+        assert!(diff.changed.is_empty());
+    }
+
+    #[test]
+    fn test_diff_reports_policy_change() {
+        let previous = NumaMap {
+            ranges: vec![range(0x1000, "default", &[(0, 10)])],
+        };
+        let current = NumaMap {
+            ranges: vec![range(0x1000, "interleave", &[(0, 10)])],
+        };
+
+        let diff = current.diff(&previous);
+        assert_eq!(diff.changed.len(), 1);
+        assert_eq!(diff.changed[0].previous_policy, Some("default".to_string()));
+    }
+}
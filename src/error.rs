@@ -0,0 +1,98 @@
+//! Structured parse errors.
+//!
+//! Malformed tokens in a `numa_maps` line used to be printed to stderr, which does not
+//! compose inside a library embedded in a daemon or a test. This module gives callers a
+//! typed error to collect, log, or propagate instead.
+
+use std::fmt;
+
+/// Error returned when parsing a single `key=value` token of a `numa_maps` line into a
+/// [`Property`](crate::Property) fails.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PropertyParseError {
+    /// The key is not a recognized property.
+    UnknownKey(String),
+    /// The value for a recognized key could not be parsed as an integer.
+    InvalidValue {
+        /// The key whose value failed to parse.
+        key: String,
+        /// The text that failed to parse.
+        value: String,
+    },
+}
+
+impl fmt::Display for PropertyParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownKey(key) => write!(f, "unknown key: {key}"),
+            Self::InvalidValue { key, value } => write!(f, "invalid value for {key}: {value}"),
+        }
+    }
+}
+
+impl std::error::Error for PropertyParseError {}
+
+/// Error describing a single malformed token encountered while parsing a `numa_maps`
+/// line, carrying enough context to log or report it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    /// The 1-based line number the offending token was found on.
+    pub line: usize,
+    /// The raw token that failed to parse.
+    pub token: String,
+    /// Why the token could not be parsed.
+    pub reason: PropertyParseError,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {}: failed to parse \"{}\": {}",
+            self.line, self.token, self.reason
+        )
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Error returned by the strict parsing entry points (e.g.
+/// [`NumaMap::from_file_strict`](crate::NumaMap::from_file_strict)), which fail on the
+/// first malformed token instead of collecting it.
+#[derive(Debug)]
+pub enum Error {
+    /// Failed to read the underlying file or stream.
+    Io(std::io::Error),
+    /// The first malformed token encountered.
+    Parse(ParseError),
+}
+
+impl From<std::io::Error> for Error {
+    fn from(err: std::io::Error) -> Self {
+        Self::Io(err)
+    }
+}
+
+impl From<ParseError> for Error {
+    fn from(err: ParseError) -> Self {
+        Self::Parse(err)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "{err}"),
+            Self::Parse(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            Self::Parse(err) => Some(err),
+        }
+    }
+}
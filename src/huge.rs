@@ -0,0 +1,139 @@
+//! Huge-page size discovery and `MAP_HUGE_*` flag computation.
+//!
+//! [`Property::Huge`](crate::Property::Huge) only flags that a region is backed by huge
+//! pages, not which size. This module enumerates the sizes the running kernel supports
+//! and computes the `mmap` flag bits needed to request a given size, so callers can
+//! correlate a parsed `Huge` region with a configured huge-page pool.
+
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// Bit shift at which the kernel encodes a huge page size into `mmap`'s `flags` argument.
+const MAP_HUGE_SHIFT: u32 = 26;
+
+/// Directory exposing the kernel's configured huge-page pools, one subdirectory per size.
+const HUGEPAGES_DIR: &str = "/sys/kernel/mm/hugepages";
+
+/// A huge-page size supported by the running kernel.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct HugePageSize {
+    kb: usize,
+}
+
+impl HugePageSize {
+    /// The common 2 MB huge-page size.
+    pub const SIZE_2MB: Self = Self { kb: 2 * 1024 };
+    /// The common 1 GB huge-page size.
+    pub const SIZE_1GB: Self = Self { kb: 1024 * 1024 };
+
+    /// Constructs a [`HugePageSize`] from a size in kilobytes.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `kb` is zero or not a power of two, since the kernel encodes a
+    /// huge page size as the `log2` of its size in bytes shifted by `MAP_HUGE_SHIFT`, and
+    /// such sizes have no representation otherwise.
+    pub fn from_kb(kb: usize) -> Result<Self, NotPowerOfTwo> {
+        if kb == 0 || !kb.is_power_of_two() {
+            return Err(NotPowerOfTwo(kb));
+        }
+        Ok(Self { kb })
+    }
+
+    /// Constructs a [`HugePageSize`] from a [`Property::Kernelpagesize`](crate::Property::Kernelpagesize)
+    /// value, which is reported in bytes.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the size, converted to kilobytes, is not a power of two.
+    pub fn from_bytes(bytes: usize) -> Result<Self, NotPowerOfTwo> {
+        Self::from_kb(bytes / 1024)
+    }
+
+    /// This size in kilobytes.
+    #[must_use]
+    pub fn kb(&self) -> usize {
+        self.kb
+    }
+
+    /// The `MAP_HUGE_*` flag bits the kernel expects in `mmap`'s `flags` argument to
+    /// request this huge page size.
+    ///
+    /// The kernel encodes `log2` of the size in bytes, not kilobytes, so this adds the
+    /// 10 bits of `log2(1024)` back on top of `log2(self.kb)`.
+    #[must_use]
+    pub fn mmap_flag(&self) -> i32 {
+        let log2_kb = (usize::BITS - self.kb.leading_zeros() - 1) as i32;
+        let log2_bytes = log2_kb + 10;
+        log2_bytes << MAP_HUGE_SHIFT
+    }
+
+    /// Enumerates the huge-page sizes the running kernel supports, by reading the
+    /// `hugepages-<kB>kB` subdirectories of `/sys/kernel/mm/hugepages`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the directory cannot be read, e.g. because the kernel was
+    /// built without huge page support.
+    pub fn available() -> io::Result<Vec<Self>> {
+        Self::available_in(Path::new(HUGEPAGES_DIR))
+    }
+
+    fn available_in(dir: &Path) -> io::Result<Vec<Self>> {
+        let mut sizes = Vec::new();
+        for entry in fs::read_dir(dir)? {
+            let name = entry?.file_name();
+            let name = name.to_string_lossy();
+            let kb = name
+                .strip_prefix("hugepages-")
+                .and_then(|s| s.strip_suffix("kB"))
+                .and_then(|s| s.parse().ok());
+            if let Some(size) = kb.and_then(|kb| Self::from_kb(kb).ok()) {
+                sizes.push(size);
+            }
+        }
+        sizes.sort();
+        Ok(sizes)
+    }
+}
+
+/// Error returned when a huge page size is not a power of two, and therefore cannot be
+/// encoded into `MAP_HUGE_*` flags.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NotPowerOfTwo(usize);
+
+impl fmt::Display for NotPowerOfTwo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} kB is not a power-of-two huge page size", self.0)
+    }
+}
+
+impl std::error::Error for NotPowerOfTwo {}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_from_kb_rejects_non_power_of_two() {
+        assert!(HugePageSize::from_kb(0).is_err());
+        assert!(HugePageSize::from_kb(3).is_err());
+        assert!(HugePageSize::from_kb(2048).is_ok());
+    }
+
+    #[test]
+    fn test_mmap_flag() {
+        assert_eq!(HugePageSize::from_kb(1).unwrap().mmap_flag(), 10 << MAP_HUGE_SHIFT);
+        assert_eq!(HugePageSize::from_kb(2).unwrap().mmap_flag(), 11 << MAP_HUGE_SHIFT);
+        // Real kernel constants, see `include/uapi/linux/mman.h`.
+        assert_eq!(HugePageSize::SIZE_2MB.mmap_flag(), 21 << MAP_HUGE_SHIFT);
+        assert_eq!(HugePageSize::SIZE_1GB.mmap_flag(), 30 << MAP_HUGE_SHIFT);
+    }
+
+    #[test]
+    fn test_from_bytes() {
+        assert_eq!(HugePageSize::from_bytes(2 * 1024 * 1024).unwrap(), HugePageSize::SIZE_2MB);
+    }
+}
@@ -1,6 +1,14 @@
 #![doc = include_str!("../README.md")]
 #![deny(missing_docs)]
 
+pub mod diff;
+pub mod error;
+pub mod huge;
+
+pub use diff::{NumaDiff, RangeDiff};
+pub use error::{Error, ParseError, PropertyParseError};
+
+use std::collections::BTreeMap;
 use std::fs::File;
 use std::io::{BufRead, BufReader};
 use std::path::{Path, PathBuf};
@@ -68,10 +76,31 @@ impl Property {
             Kernelpagesize(_) => None,
         }
     }
+
+    fn anon_size(&self) -> Option<usize> {
+        match self {
+            Self::Anon(size) => Some(*size),
+            _ => None,
+        }
+    }
+
+    fn dirty_size(&self) -> Option<usize> {
+        match self {
+            Self::Dirty(size) => Some(*size),
+            _ => None,
+        }
+    }
+
+    fn mapped_size(&self) -> Option<usize> {
+        match self {
+            Self::Mapped(size) => Some(*size),
+            _ => None,
+        }
+    }
 }
 
 impl FromStr for Property {
-    type Err = String;
+    type Err = PropertyParseError;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let (key, val) = if let Some(index) = s.find('=') {
@@ -80,33 +109,35 @@ impl FromStr for Property {
         } else {
             (s, None)
         };
+        let invalid = |val: &str| PropertyParseError::InvalidValue {
+            key: key.to_string(),
+            value: val.to_string(),
+        };
         match (key, val) {
             (key, Some(val)) if key.starts_with('N') => {
-                let node = key[1..].parse().map_err(|e| format!("{e}"))?;
-                let count = val.parse().map_err(|e| format!("{e}"))?;
+                let node = key[1..]
+                    .parse()
+                    .map_err(|_| PropertyParseError::UnknownKey(format!("{key}={val}")))?;
+                let count = val.parse().map_err(|_| invalid(val))?;
                 Ok(Self::N(node, count))
             }
             ("file", Some(val)) => Ok(Self::File(PathBuf::from(val))),
             ("heap", _) => Ok(Self::Heap),
             ("stack", _) => Ok(Self::Stack),
             ("huge", _) => Ok(Self::Huge),
-            ("anon", Some(val)) => val.parse().map(Self::Anon).map_err(|e| format!("{e}")),
-            ("dirty", Some(val)) => val.parse().map(Self::Dirty).map_err(|e| format!("{e}")),
-            ("mapped", Some(val)) => val.parse().map(Self::Mapped).map_err(|e| format!("{e}")),
-            ("mapmax", Some(val)) => val.parse().map(Self::MapMax).map_err(|e| format!("{e}")),
-            ("swapcache", Some(val)) => {
-                val.parse().map(Self::SwapCache).map_err(|e| format!("{e}"))
-            }
-            ("active", Some(val)) => val.parse().map(Self::Active).map_err(|e| format!("{e}")),
-            ("writeback", Some(val)) => {
-                val.parse().map(Self::Writeback).map_err(|e| format!("{e}"))
-            }
+            ("anon", Some(val)) => val.parse().map(Self::Anon).map_err(|_| invalid(val)),
+            ("dirty", Some(val)) => val.parse().map(Self::Dirty).map_err(|_| invalid(val)),
+            ("mapped", Some(val)) => val.parse().map(Self::Mapped).map_err(|_| invalid(val)),
+            ("mapmax", Some(val)) => val.parse().map(Self::MapMax).map_err(|_| invalid(val)),
+            ("swapcache", Some(val)) => val.parse().map(Self::SwapCache).map_err(|_| invalid(val)),
+            ("active", Some(val)) => val.parse().map(Self::Active).map_err(|_| invalid(val)),
+            ("writeback", Some(val)) => val.parse().map(Self::Writeback).map_err(|_| invalid(val)),
             ("kernelpagesize_kB", Some(val)) => val
                 .parse()
                 .map(|sz: usize| Self::Kernelpagesize(sz << 10))
-                .map_err(|e| format!("{e}")),
-            (key, None) => Err(format!("unknown key: {key}")),
-            (key, Some(val)) => Err(format!("unknown key/value: {key}={val}")),
+                .map_err(|_| invalid(val)),
+            (key, None) => Err(PropertyParseError::UnknownKey(key.to_string())),
+            (key, Some(val)) => Err(PropertyParseError::UnknownKey(format!("{key}={val}"))),
         }
     }
 }
@@ -123,11 +154,12 @@ pub struct Range {
 }
 
 impl Range {
-    /// Parse a numa map line. Prints errors to stderr.
+    /// Parse a numa map line, accumulating malformed tokens into `errors` instead of
+    /// failing on them.
     ///
     /// Returns no value if the line does not contain an address, or the address is
     /// malformed.
-    fn parse(line: &str) -> Option<Self> {
+    fn parse(line: &str, line_number: usize, errors: &mut Vec<ParseError>) -> Option<Self> {
         let mut parts = line.split_whitespace();
         let address = <usize>::from_str_radix(parts.next()?, 16).ok()?;
         let policy = parts.next()?.to_string();
@@ -135,7 +167,11 @@ impl Range {
         for part in parts {
             match part.parse::<Property>() {
                 Ok(property) => properties.push(property),
-                Err(err) => eprintln!("Failed to parse numa_map entry \"{part}\": {err}"),
+                Err(reason) => errors.push(ParseError {
+                    line: line_number,
+                    token: part.to_string(),
+                    reason,
+                }),
             }
         }
         Some(Self {
@@ -158,6 +194,45 @@ impl Range {
             self.properties = properties;
         }
     }
+
+    /// Computes this range's resident bytes/anon/dirty/mapped, apportioned per node.
+    fn node_stats(&self) -> BTreeMap<usize, NodeStats> {
+        let resident: usize = self
+            .properties
+            .iter()
+            .filter_map(|p| match p {
+                Property::N(_, size) => Some(*size),
+                _ => None,
+            })
+            .sum();
+        let anon = self.property_total(Property::anon_size);
+        let dirty = self.property_total(Property::dirty_size);
+        let mapped = self.property_total(Property::mapped_size);
+
+        let mut stats = BTreeMap::new();
+        for property in &self.properties {
+            if let Property::N(node, size) = *property {
+                let share = |total: usize| {
+                    if resident == 0 {
+                        0
+                    } else {
+                        ((total as u128 * size as u128) / resident as u128) as usize
+                    }
+                };
+                *stats.entry(node).or_insert(NodeStats::default()) += NodeStats {
+                    resident: size,
+                    anon: share(anon),
+                    dirty: share(dirty),
+                    mapped: share(mapped),
+                };
+            }
+        }
+        stats
+    }
+
+    fn property_total(&self, f: impl Fn(&Property) -> Option<usize>) -> usize {
+        self.properties.iter().filter_map(f).sum()
+    }
 }
 
 /// A whole `numu_maps` file.
@@ -172,33 +247,301 @@ impl NumaMap {
     ///
     /// Parses the contents and returns them as [`NumaMap`]. Each line translates
     /// to an entry in [`NumaMap::ranges`], which stores the properties gathered
-    /// from the file as [`Property`].
+    /// from the file as [`Property`]. Tokens that fail to parse are silently dropped; see
+    /// [`NumaMap::from_file_collecting`] to collect them instead, or
+    /// [`NumaMap::from_file_strict`] to fail on the first one.
     ///
     /// # Errors
     ///
     /// Returns an error if it fails to read the file.
     pub fn from_file<P: AsRef<Path>>(path: P) -> std::io::Result<Self> {
+        let (map, _errors) = Self::from_file_collecting(path)?;
+        Ok(map)
+    }
+
+    /// Like [`NumaMap::from_file`], but also returns every [`ParseError`] encountered
+    /// along the way, so callers can log or surface them as they see fit.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if it fails to read the file.
+    pub fn from_file_collecting<P: AsRef<Path>>(path: P) -> std::io::Result<(Self, Vec<ParseError>)> {
         let file = File::open(path)?;
-        let reader = BufReader::new(file);
+        Self::from_reader_collecting(BufReader::new(file))
+    }
 
+    /// Like [`NumaMap::from_file`], but fails on the first malformed token.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file cannot be read, or if any token fails to parse.
+    pub fn from_file_strict<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let file = File::open(path)?;
+        Self::from_reader_strict(BufReader::new(file))
+    }
+
+    /// Parses a `numa_maps` buffer from any [`BufRead`] source.
+    ///
+    /// Useful for parsing buffers already held in memory, e.g. a test fixture or an
+    /// in-memory capture, without going through [`File`]. Tokens that fail to parse are
+    /// silently dropped; see [`NumaMap::from_reader_collecting`] to collect them instead,
+    /// or [`NumaMap::from_reader_strict`] to fail on the first one.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if reading from `reader` fails.
+    pub fn from_reader<R: BufRead>(reader: R) -> std::io::Result<Self> {
+        let (map, _errors) = Self::from_reader_collecting(reader)?;
+        Ok(map)
+    }
+
+    /// Like [`NumaMap::from_reader`], but also returns every [`ParseError`] encountered
+    /// along the way, so callers can log or surface them as they see fit.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if reading from `reader` fails.
+    pub fn from_reader_collecting<R: BufRead>(reader: R) -> std::io::Result<(Self, Vec<ParseError>)> {
         let mut ranges = Vec::new();
-        for line in reader.lines() {
-            if let Some(range) = Range::parse(&(line?)) {
+        let mut errors = Vec::new();
+        for (line_number, line) in reader.lines().enumerate() {
+            if let Some(range) = Range::parse(&(line?), line_number + 1, &mut errors) {
                 ranges.push(range);
             }
         }
-        Ok(Self { ranges })
+        Ok((Self { ranges }, errors))
+    }
+
+    /// Like [`NumaMap::from_reader`], but fails on the first malformed token.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if reading from `reader` fails, or if any token fails to parse.
+    pub fn from_reader_strict<R: BufRead>(reader: R) -> Result<Self, Error> {
+        let (map, mut errors) = Self::from_reader_collecting(reader)?;
+        if !errors.is_empty() {
+            return Err(errors.remove(0).into());
+        }
+        Ok(map)
+    }
+
+    /// Reads the `numa_maps` file of the process with the given `pid`, i.e.
+    /// `/proc/<pid>/numa_maps`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the process does not exist, has no `numa_maps` (e.g. it has no
+    /// mappings left), or cannot be read due to permissions.
+    pub fn from_pid(pid: u32) -> std::io::Result<Self> {
+        Self::from_file(format!("/proc/{pid}/numa_maps"))
+    }
+
+    /// Like [`NumaMap::from_pid`], but also returns every [`ParseError`] encountered along
+    /// the way, so callers can log or surface them as they see fit.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the process does not exist, has no `numa_maps` (e.g. it has no
+    /// mappings left), or cannot be read due to permissions.
+    pub fn from_pid_collecting(pid: u32) -> std::io::Result<(Self, Vec<ParseError>)> {
+        Self::from_file_collecting(format!("/proc/{pid}/numa_maps"))
+    }
+
+    /// Iterates over every process currently visible under `/proc`, yielding its pid
+    /// alongside its parsed `numa_maps`.
+    ///
+    /// Processes that exit, or whose `numa_maps` cannot be read (e.g. due to permissions),
+    /// while iterating are silently skipped, since races with process lifetime are
+    /// expected when walking `/proc`. Malformed tokens within a process's `numa_maps` are
+    /// silently dropped here too; use [`NumaMap::from_pid_collecting`] directly if you need
+    /// them.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if `/proc` itself cannot be read.
+    pub fn all_processes() -> std::io::Result<AllProcesses> {
+        Ok(AllProcesses {
+            entries: std::fs::read_dir("/proc")?,
+        })
+    }
+
+    /// Visits every [`Range`] in this map, in file order.
+    ///
+    /// Callers that need a different accumulator than [`NumaMap::summarize`] can fold over
+    /// the same ranges without copying them.
+    pub fn for_each_range<F: FnMut(&Range)>(&self, mut f: F) {
+        for range in &self.ranges {
+            f(range);
+        }
+    }
+
+    /// Folds over every `N(node, size)` property of every range, in file order.
+    ///
+    /// Values should be normalized first (see [`Range::normalize`]) so that `size` is a
+    /// byte count rather than a page count.
+    pub fn fold_by_node<T>(&self, init: T, mut fold: impl FnMut(T, usize, usize) -> T) -> T {
+        let mut acc = init;
+        for range in &self.ranges {
+            for property in &range.properties {
+                if let Property::N(node, size) = *property {
+                    acc = fold(acc, node, size);
+                }
+            }
+        }
+        acc
+    }
+
+    /// Aggregates this map's ranges into per-node totals, plus breakdowns by backing file
+    /// and by region kind.
+    ///
+    /// Ranges should be normalized first (see [`Range::normalize`]) so that the returned
+    /// byte counts are meaningful. For ranges resident on more than one node (e.g. an
+    /// interleaved policy), the range's anon/dirty/mapped byte counts are apportioned
+    /// across its nodes in proportion to each node's share of the range's resident bytes,
+    /// since `numa_maps` itself does not record those counts per node.
+    #[must_use]
+    pub fn summarize(&self) -> Summary {
+        let mut summary = Summary::default();
+        for range in &self.ranges {
+            let stats = range.node_stats();
+            if stats.is_empty() {
+                continue;
+            }
+
+            let by_node = &mut summary.by_node;
+            for (node, stat) in &stats {
+                *by_node.entry(*node).or_default() += *stat;
+            }
+
+            let file = range.properties.iter().find_map(|p| match p {
+                Property::File(path) => Some(path.clone()),
+                _ => None,
+            });
+            let by_file = summary.by_file.entry(file).or_default();
+            for (node, stat) in &stats {
+                *by_file.entry(*node).or_default() += *stat;
+            }
+
+            let kind = RegionKind::of(range);
+            let by_kind = summary.by_kind.entry(kind).or_default();
+            for (node, stat) in &stats {
+                *by_kind.entry(*node).or_default() += *stat;
+            }
+        }
+        summary
     }
 }
 
+impl FromStr for NumaMap {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match Self::from_reader_strict(s.as_bytes()) {
+            Ok(map) => Ok(map),
+            Err(Error::Parse(err)) => Err(err),
+            Err(Error::Io(_)) => unreachable!("reading from a string cannot fail"),
+        }
+    }
+}
+
+/// Iterator over every process's [`NumaMap`], produced by [`NumaMap::all_processes`].
+pub struct AllProcesses {
+    entries: std::fs::ReadDir,
+}
+
+impl Iterator for AllProcesses {
+    type Item = (u32, NumaMap);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let Ok(entry) = self.entries.next()? else {
+                continue;
+            };
+            let Some(pid) = entry.file_name().to_str().and_then(|s| s.parse().ok()) else {
+                continue;
+            };
+            if let Ok(map) = NumaMap::from_pid(pid) {
+                return Some((pid, map));
+            }
+        }
+    }
+}
+
+/// Coarse classification of a memory range's backing, used to group [`Summary`] statistics.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum RegionKind {
+    /// Backed by the process heap.
+    Heap,
+    /// Backed by a thread stack.
+    Stack,
+    /// File-backed mapping.
+    File,
+    /// Anonymous mapping that is none of the above.
+    Anonymous,
+}
+
+impl RegionKind {
+    fn of(range: &Range) -> Self {
+        let mut file = false;
+        for property in &range.properties {
+            match property {
+                Property::Heap => return Self::Heap,
+                Property::Stack => return Self::Stack,
+                Property::File(_) => file = true,
+                _ => {}
+            }
+        }
+        if file {
+            Self::File
+        } else {
+            Self::Anonymous
+        }
+    }
+}
+
+/// Resident memory statistics for a single NUMA node, as produced by [`NumaMap::summarize`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct NodeStats {
+    /// Bytes resident on this node (sum of `N` properties).
+    pub resident: usize,
+    /// Anonymous bytes attributed to this node.
+    pub anon: usize,
+    /// Dirty bytes attributed to this node.
+    pub dirty: usize,
+    /// Mapped (file-backed) bytes attributed to this node.
+    pub mapped: usize,
+}
+
+impl std::ops::AddAssign for NodeStats {
+    fn add_assign(&mut self, other: Self) {
+        self.resident += other.resident;
+        self.anon += other.anon;
+        self.dirty += other.dirty;
+        self.mapped += other.mapped;
+    }
+}
+
+/// Aggregated view over a [`NumaMap`], produced by [`NumaMap::summarize`].
+#[derive(Debug, Default, Clone)]
+pub struct Summary {
+    /// Per-node statistics, keyed by NUMA node id.
+    pub by_node: BTreeMap<usize, NodeStats>,
+    /// Per-node statistics, further split by the file backing the region (`None` for
+    /// anonymous regions).
+    pub by_file: BTreeMap<Option<PathBuf>, BTreeMap<usize, NodeStats>>,
+    /// Per-node statistics, split by [`RegionKind`].
+    pub by_kind: BTreeMap<RegionKind, BTreeMap<usize, NodeStats>>,
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
 
     #[test]
     fn test_read() -> std::io::Result<()> {
-        let map = NumaMap::from_file("resources/numa_maps")?;
+        let (map, errors) = NumaMap::from_file_collecting("resources/numa_maps")?;
 
+        assert!(errors.is_empty());
         assert_eq!(map.ranges.len(), 23);
 
         use Property::{Active, Anon, Dirty, File, Heap, Kernelpagesize, MapMax, Mapped, Stack, N};
@@ -443,7 +786,9 @@ mod test {
         use Property::*;
 
         let line = "7fbd0c10f000 default anon=5 dirty=5 active=1 N0=5 kernelpagesize_kB=4";
-        let mut range = Range::parse(line).unwrap();
+        let mut errors = Vec::new();
+        let mut range = Range::parse(line, 1, &mut errors).unwrap();
+        assert!(errors.is_empty());
         range.normalize();
         range.properties.sort();
         let expected = vec![
@@ -462,4 +807,40 @@ mod test {
         assert!(map.ranges.len() > 0);
         Ok(())
     }
+
+    #[test]
+    fn test_from_str_reports_bad_token() {
+        let result = "7fbd0c10f000 default nonsense=1".parse::<NumaMap>();
+        let err = match result {
+            Err(err) => err,
+            Ok(_) => panic!("expected a parse error"),
+        };
+        assert_eq!(err.line, 1);
+        assert_eq!(err.token, "nonsense=1");
+    }
+
+    #[test]
+    fn test_summarize_does_not_overflow_on_large_byte_counts() {
+        use Property::{Anon, Dirty, Mapped, N};
+
+        let map = NumaMap {
+            ranges: vec![Range {
+                address: 0,
+                policy: "default".to_string(),
+                properties: vec![
+                    N(0, 4 << 30),
+                    Anon(8 << 30),
+                    Dirty(8 << 30),
+                    Mapped(8 << 30),
+                ],
+            }],
+        };
+
+        let summary = map.summarize();
+        let node = summary.by_node.get(&0).unwrap();
+        assert_eq!(node.resident, 4 << 30);
+        assert_eq!(node.anon, 8 << 30);
+        assert_eq!(node.dirty, 8 << 30);
+        assert_eq!(node.mapped, 8 << 30);
+    }
 }